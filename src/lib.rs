@@ -1,53 +1,329 @@
 pub mod pixels;
+mod png;
+pub mod y4m;
 
-use pixels::Rgb;
+use pixels::{Hsv, PixelFormat, Rgb};
 
 use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
 use std::ops::{Index, IndexMut};
 use std::process::{Child, ChildStdin, Command, Stdio};
 
-/// Represents an entire video to be piped into ffmpeg.
-pub struct Video<P> {
-    buffer: Frame<P>,
-    resolution: (usize, usize),
-    fps: u32,
-    child: Child,
-    stdin: BufWriter<ChildStdin>,
+/// The video codec ffmpeg should encode with.
+///
+/// Each variant maps to the `-c:v` value ffmpeg expects; see [`Codec::ffmpeg_name`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Codec {
+    /// `libx264`, i.e. H.264/AVC. This is the default.
+    #[default]
+    H264,
+    /// `libx265`, i.e. H.265/HEVC.
+    H265,
+    /// `libsvtav1`, i.e. AV1 via SVT-AV1.
+    Av1,
 }
 
-/// Represents a single frame that belongs to a Video struct.
-pub struct Frame<P> {
-    data: Vec<P>,
+impl Codec {
+    /// Returns the ffmpeg `-c:v` value for this codec.
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            Codec::H264 => "libx264",
+            Codec::H265 => "libx265",
+            Codec::Av1 => "libsvtav1",
+        }
+    }
+}
+
+/// Hardware-accelerated encoding backends, available behind the `vaapi` feature.
+///
+/// Frames are still written to ffmpeg in the pixel type's own raw format (see
+/// [`PixelFormat`]); only the output-side codec and the init/filter arguments change.
+#[cfg(feature = "vaapi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "vaapi")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HwAccel {
+    /// Encode via VAAPI, using the given render device (e.g. `/dev/dri/renderD128`).
+    /// Set `hevc` to encode `hevc_vaapi` instead of `h264_vaapi`.
+    Vaapi { device: String, hevc: bool },
+    /// Encode via NVENC (`h264_nvenc`).
+    Nvenc,
+}
+
+/// Where the audio track muxed into the output comes from.
+enum AudioSource {
+    /// Mux in an existing audio file already on disk.
+    File(String),
+    /// Accept raw PCM samples pushed at runtime via [`Video::write_audio`].
+    Piped { sample_rate: u32, channels: u16 },
+}
+
+/// Which channel(s) of a stereo audio source to keep in the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioChannel {
+    /// Keep both channels untouched. This is the default.
+    Both,
+    /// Extract only the left channel (`c0`) as mono, e.g. for a lavalier mic
+    /// recorded on one channel of a stereo source.
+    Left,
+    /// Extract only the right channel (`c1`) as mono.
+    Right,
+}
+
+impl AudioChannel {
+    fn pan_filter(&self) -> Option<&'static str> {
+        match self {
+            AudioChannel::Both => None,
+            AudioChannel::Left => Some("pan=mono|c0=c0"),
+            AudioChannel::Right => Some("pan=mono|c0=c1"),
+        }
+    }
+}
+
+/// Builder for configuring the ffmpeg invocation behind a [`Video`].
+///
+/// Defaults match the historical behavior of `Video::new`: H.264 video muxed
+/// into an mp4 container, `yuv420p` output, no audio, and ffmpeg's own
+/// default CRF/preset for the chosen codec.
+pub struct VideoBuilder<P> {
     resolution: (usize, usize),
+    fps: u32,
+    codec: Codec,
+    container: String,
+    pix_fmt: String,
+    crf: Option<u8>,
+    preset: Option<String>,
+    #[cfg(feature = "vaapi")]
+    hw_accel: Option<HwAccel>,
+    audio: Option<AudioSource>,
+    audio_channel: AudioChannel,
+    _marker: PhantomData<P>,
 }
 
-impl<P: Default> Video<P>
-where
-    for<'a> &'a P: Into<Rgb>,
-{
-    /// Creates a new empty video with the given resolution and FPS.
-    pub fn new<S: std::fmt::Display>(resolution: (usize, usize), fps: u32, filename: S) -> Self {
-        let (x, y) = resolution;
+impl<P: Default + PixelFormat> VideoBuilder<P> {
+    /// Creates a new builder with the given resolution and FPS, and every other
+    /// setting at its default.
+    pub fn new(resolution: (usize, usize), fps: u32) -> Self {
+        Self {
+            resolution,
+            fps,
+            codec: Codec::default(),
+            container: "mp4".to_string(),
+            pix_fmt: "yuv420p".to_string(),
+            crf: None,
+            preset: None,
+            #[cfg(feature = "vaapi")]
+            hw_accel: None,
+            audio: None,
+            audio_channel: AudioChannel::Both,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the video codec to encode with. Defaults to [`Codec::H264`].
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the output container/file extension, without the leading dot. Defaults to `mp4`.
+    pub fn container<S: Into<String>>(mut self, container: S) -> Self {
+        self.container = container.into();
+        self
+    }
+
+    /// Sets the `-pix_fmt` ffmpeg should encode the output with. Defaults to `yuv420p`.
+    ///
+    /// Ignored when [`HwAccel::Vaapi`] is selected via [`VideoBuilder::hw_accel`],
+    /// since that encoder takes frames off the hardware surface the input filter
+    /// uploads to, not a software pixel format. [`HwAccel::Nvenc`] never uploads
+    /// frames to a hardware surface in this crate, so it still honors this setting.
+    pub fn pix_fmt<S: Into<String>>(mut self, pix_fmt: S) -> Self {
+        self.pix_fmt = pix_fmt.into();
+        self
+    }
+
+    /// Sets the encoder's CRF (quality) value. Left unset, ffmpeg's own default is used.
+    ///
+    /// Ignored when a [`VideoBuilder::hw_accel`] backend is selected; `*_vaapi`/`*_nvenc`
+    /// encoders don't support `-crf`.
+    pub fn crf(mut self, crf: u8) -> Self {
+        self.crf = Some(crf);
+        self
+    }
+
+    /// Sets the encoder preset (e.g. `medium`, `slow`). Left unset, ffmpeg's own default is used.
+    pub fn preset<S: Into<String>>(mut self, preset: S) -> Self {
+        self.preset = Some(preset.into());
+        self
+    }
+
+    /// Selects a hardware-accelerated encoding backend instead of the software codec
+    /// set via [`VideoBuilder::codec`]. Requires the `vaapi` feature.
+    #[cfg(feature = "vaapi")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "vaapi")))]
+    pub fn hw_accel(mut self, hw_accel: HwAccel) -> Self {
+        self.hw_accel = Some(hw_accel);
+        self
+    }
+
+    /// Mux in an existing audio file from disk, instead of leaving the output silent.
+    pub fn audio_file<S: Into<String>>(mut self, path: S) -> Self {
+        self.audio = Some(AudioSource::File(path.into()));
+        self
+    }
+
+    /// Mux in audio pushed at runtime via [`Video::write_audio`], instead of leaving
+    /// the output silent. `sample_rate` and `channels` describe the raw `s16le` PCM
+    /// that will be written to that method.
+    pub fn piped_audio(mut self, sample_rate: u32, channels: u16) -> Self {
+        self.audio = Some(AudioSource::Piped {
+            sample_rate,
+            channels,
+        });
+        self
+    }
+
+    /// Selects a single channel out of a stereo audio source. Defaults to [`AudioChannel::Both`].
+    pub fn audio_channel(mut self, channel: AudioChannel) -> Self {
+        self.audio_channel = channel;
+        self
+    }
+
+    /// Determines the `-c:v` value, any extra pre-input args and input-side video
+    /// filter a hardware backend needs, and whether the backend still takes
+    /// `-pix_fmt` on the output side. Falls back to the configured software codec
+    /// (which always owns `-pix_fmt`) when no hardware backend is selected (or the
+    /// `vaapi` feature is disabled).
+    fn encode_plan(&self) -> (Vec<String>, Option<String>, String, bool) {
+        #[cfg(feature = "vaapi")]
+        if let Some(hw_accel) = &self.hw_accel {
+            return match hw_accel {
+                HwAccel::Vaapi { device, hevc } => (
+                    vec!["-vaapi_device".to_string(), device.clone()],
+                    Some("format=nv12,hwupload".to_string()),
+                    if *hevc { "hevc_vaapi" } else { "h264_vaapi" }.to_string(),
+                    // Frames are uploaded to a hardware surface by the filter above;
+                    // there's no software pixel format left for `-pix_fmt` to set.
+                    false,
+                ),
+                // Nvenc never gets a hwupload filter in this crate: frames stay on
+                // the software side exactly like the non-hw-accel path, so it still
+                // honors `-pix_fmt` (e.g. `yuv444p`/`p010le` where the build supports it).
+                HwAccel::Nvenc => (Vec::new(), None, "h264_nvenc".to_string(), true),
+            };
+        }
+
+        (Vec::new(), None, self.codec.ffmpeg_name().to_string(), true)
+    }
+
+    /// Whether a hardware-accelerated backend is selected, i.e. whether `-crf`
+    /// (which none of them support) should be skipped.
+    fn hw_accel_active(&self) -> bool {
+        #[cfg(feature = "vaapi")]
+        {
+            self.hw_accel.is_some()
+        }
+        #[cfg(not(feature = "vaapi"))]
+        {
+            false
+        }
+    }
+
+    /// Spawns ffmpeg with the configured options and returns the resulting [`Video`].
+    pub fn build<S: std::fmt::Display>(self, filename: S) -> Video<P> {
+        let (x, y) = self.resolution;
+        let (pre_input_args, input_filter, codec_name, owns_pix_fmt) = self.encode_plan();
+
+        let mut args = vec!["-y".to_string()];
+        args.extend(pre_input_args);
+        args.extend(["-f", "rawvideo", "-pixel_format"].map(String::from));
+        args.push(P::FFMPEG_NAME.to_string());
+        args.push("-video_size".to_string());
+        args.push(format!("{}x{}", x, y));
+        args.push("-framerate".to_string());
+        args.push(self.fps.to_string());
+        args.push("-i".to_string());
+        args.push("-".to_string());
+
+        if let Some(filter) = input_filter {
+            args.push("-vf".to_string());
+            args.push(filter);
+        }
+
+        let mut audio_fifo_guard = None;
+        if let Some(audio) = &self.audio {
+            match audio {
+                AudioSource::File(path) => {
+                    args.push("-i".to_string());
+                    args.push(path.clone());
+                }
+                AudioSource::Piped {
+                    sample_rate,
+                    channels,
+                } => {
+                    let path = new_fifo_path();
+                    make_fifo(&path);
+                    let guard = FifoGuard::new(path);
+
+                    args.push("-f".to_string());
+                    args.push("s16le".to_string());
+                    args.push("-ar".to_string());
+                    args.push(sample_rate.to_string());
+                    args.push("-ac".to_string());
+                    args.push(channels.to_string());
+                    args.push("-i".to_string());
+                    args.push(
+                        guard
+                            .0
+                            .as_ref()
+                            .expect("fifo guard not yet released")
+                            .display()
+                            .to_string(),
+                    );
+
+                    audio_fifo_guard = Some(guard);
+                }
+            }
+
+            args.push("-map".to_string());
+            args.push("0:v:0".to_string());
+            args.push("-map".to_string());
+            args.push("1:a:0".to_string());
+        }
+
+        args.push("-c:v".to_string());
+        args.push(codec_name);
+
+        if owns_pix_fmt {
+            args.push("-pix_fmt".to_string());
+            args.push(self.pix_fmt.clone());
+        }
+
+        if !self.hw_accel_active() {
+            if let Some(crf) = self.crf {
+                args.push("-crf".to_string());
+                args.push(crf.to_string());
+            }
+        }
+
+        if let Some(preset) = &self.preset {
+            args.push("-preset".to_string());
+            args.push(preset.clone());
+        }
+
+        if self.audio.is_some() {
+            if let Some(filter) = self.audio_channel.pan_filter() {
+                args.push("-af".to_string());
+                args.push(filter.to_string());
+            }
+        } else {
+            args.push("-an".to_string());
+        }
+
+        args.push(format!("{}.{}", filename, self.container));
+
         let mut child = Command::new("ffmpeg")
-            .args([
-                "-y",
-                "-f",
-                "rawvideo",
-                "-pixel_format",
-                "rgb24",
-                "-video_size",
-                &format!("{}x{}", x, y),
-                "-framerate",
-                &fps.to_string(),
-                "-i",
-                "-",
-                "-c:v",
-                "libx264",
-                "-pix_fmt",
-                "yuv420p",
-                "-an",
-                &format!("{}.mp4", filename),
-            ])
+            .args(args)
             .stdin(Stdio::piped())
             .spawn()
             .expect("couldn't spawn child process for ffmpeg");
@@ -59,18 +335,120 @@ where
 
         let stdin = BufWriter::new(stdin);
 
-        Self {
-            buffer: Frame::new(resolution),
-            resolution,
-            fps,
+        // Opening the fifo for writing blocks until ffmpeg opens its read end, which
+        // it only does once it reaches this input in its (strictly ordered) probing
+        // of `-i` arguments — i.e. after probing the rawvideo pipe above. Opening it
+        // here, before `build()` has even returned, would risk a deadlock: ffmpeg
+        // blocked reading input 0 for frame data nothing has written yet, and us
+        // blocked opening input 1 waiting for ffmpeg to get there. Defer the open to
+        // the first `write_audio` call instead, which enforces (see its doc comment)
+        // that at least one frame has already been written to ffmpeg's stdin by then.
+        let audio_fifo_path = audio_fifo_guard.map(FifoGuard::release);
+
+        Video {
+            buffer: Frame::new(self.resolution),
+            resolution: self.resolution,
+            fps: self.fps,
             child,
             stdin,
+            frame_saved: false,
+            audio_stdin: None,
+            audio_fifo_path,
         }
     }
+}
+
+/// Removes the fifo at `self.0` on drop, unless [`FifoGuard::release`] has been
+/// called. Guards against leaking the fifo file if `build()` panics between
+/// creating it and handing it off to a [`Video`].
+struct FifoGuard(Option<std::path::PathBuf>);
+
+impl FifoGuard {
+    fn new(path: std::path::PathBuf) -> Self {
+        Self(Some(path))
+    }
+
+    /// Disarms the guard and returns the fifo path, leaving cleanup of the file to
+    /// the resulting [`Video`]'s [`Video::finish`].
+    fn release(mut self) -> std::path::PathBuf {
+        self.0.take().expect("FifoGuard path taken twice")
+    }
+}
+
+impl Drop for FifoGuard {
+    fn drop(&mut self) {
+        if let Some(path) = &self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Builds a unique path for a per-video audio FIFO under the system temp directory.
+fn new_fifo_path() -> std::path::PathBuf {
+    static COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+    let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+    std::env::temp_dir().join(format!("pipeframe-audio-{}-{}.fifo", std::process::id(), n))
+}
+
+/// Creates a named pipe at `path` for piped audio input.
+#[cfg(unix)]
+fn make_fifo(path: &std::path::Path) {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    extern "C" {
+        fn mkfifo(path: *const std::os::raw::c_char, mode: u32) -> i32;
+    }
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).expect("invalid fifo path");
+
+    // rw-------
+    if unsafe { mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        panic!(
+            "failed to create audio fifo at {}: {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn make_fifo(_path: &std::path::Path) {
+    panic!("piped audio is only supported on unix platforms");
+}
+
+/// Represents an entire video to be piped into ffmpeg.
+pub struct Video<P> {
+    buffer: Frame<P>,
+    resolution: (usize, usize),
+    fps: u32,
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    frame_saved: bool,
+    audio_stdin: Option<BufWriter<std::fs::File>>,
+    audio_fifo_path: Option<std::path::PathBuf>,
+}
+
+/// Represents a single frame that belongs to a Video struct.
+pub struct Frame<P> {
+    data: Vec<P>,
+    resolution: (usize, usize),
+}
+
+impl<P: Default + PixelFormat> Video<P> {
+    /// Creates a new empty video with the given resolution and FPS, encoding with
+    /// today's defaults (H.264 in an mp4 container, `yuv420p`, no audio).
+    ///
+    /// Use [`VideoBuilder`] instead if you need to configure the codec, container,
+    /// pixel format, or quality settings.
+    pub fn new<S: std::fmt::Display>(resolution: (usize, usize), fps: u32, filename: S) -> Self {
+        VideoBuilder::new(resolution, fps).build(filename)
+    }
 
     /// Resets the frame buffer and returns a mutable reference to it.
     pub fn reset_frame(&mut self) -> &mut Frame<P> {
-        self.buffer.data.fill_with(Default::default);
+        self.buffer.reset();
 
         &mut self.buffer
     }
@@ -93,27 +471,97 @@ where
     /// Pipe the current frame into ffmpeg
     pub fn save_frame(&mut self) {
         let stdin = &mut self.stdin;
+        let mut buf = Vec::new();
 
         self.buffer.data.iter().for_each(|pixel| {
-            let buf = <&P as Into<Rgb>>::into(pixel).vals;
+            buf.clear();
+            pixel.write_raw(&mut buf);
 
             stdin
                 .write_all(&buf)
                 .expect("could not write to child stdin")
         });
+
+        self.frame_saved = true;
+    }
+
+    /// Writes a chunk of raw `s16le` PCM audio samples into the piped audio input
+    /// set up via [`VideoBuilder::piped_audio`].
+    ///
+    /// The first call opens the underlying fifo for writing, which blocks until
+    /// ffmpeg opens its read end; ffmpeg only does that after it has read frame
+    /// data from stdin to probe the video input (any buffered frame data is flushed
+    /// to it first), so **at least one [`Video::save_frame`] call must
+    /// happen before the first `write_audio` call**, or the open (and this call)
+    /// will block forever. This is a hard precondition, not just a recommendation:
+    /// it's cheap to check and not worth silently hanging on, so it's enforced
+    /// below rather than merely documented.
+    ///
+    /// # Panics
+    /// Panics if this video wasn't built with [`VideoBuilder::piped_audio`], or if
+    /// this is the first call and no frame has been saved yet via
+    /// [`Video::save_frame`].
+    pub fn write_audio(&mut self, samples: &[i16]) {
+        if self.audio_stdin.is_none() {
+            assert!(
+                self.frame_saved,
+                "write_audio called before any save_frame: opening the audio fifo here \
+                 would block forever waiting for ffmpeg to reach it, since ffmpeg only \
+                 does so after reading frame data from stdin"
+            );
+
+            self.stdin.flush().expect("could not flush child stdin");
+        }
+
+        let stdin = self.audio_stdin.get_or_insert_with(|| {
+            let path = self
+                .audio_fifo_path
+                .as_ref()
+                .expect("write_audio called on a Video with no piped audio input");
+
+            BufWriter::new(
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .expect("could not open audio fifo for writing"),
+            )
+        });
+
+        for sample in samples {
+            stdin
+                .write_all(&sample.to_le_bytes())
+                .expect("could not write to audio pipe");
+        }
     }
 
     /// Finish the video encoding operation.
     pub fn finish(mut self) {
         drop(self.stdin);
+        drop(self.audio_stdin);
         self.child
             .wait()
             .expect("failed to wait for child process to exit");
+
+        if let Some(path) = &self.audio_fifo_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl<P: Default + PixelFormat> Video<P>
+where
+    for<'a> &'a P: Into<Rgb>,
+{
+    /// Saves the current frame buffer as a PNG at `filename` (with a `.png` extension
+    /// appended), without affecting the piped video stream.
+    pub fn snapshot<S: std::fmt::Display>(&self, filename: S) -> std::io::Result<()> {
+        let file = std::fs::File::create(format!("{}.png", filename))?;
+        self.buffer.save_png(file)
     }
 }
 
 impl<P: Default> Frame<P> {
-    fn new(resolution: (usize, usize)) -> Self {
+    pub(crate) fn new(resolution: (usize, usize)) -> Self {
         let (x, y) = resolution;
         let mut data = Vec::with_capacity(x * y);
 
@@ -123,6 +571,11 @@ impl<P: Default> Frame<P> {
 
         Self { data, resolution }
     }
+
+    /// Resets every pixel in the frame back to its default value.
+    pub(crate) fn reset(&mut self) {
+        self.data.fill_with(Default::default);
+    }
 }
 
 impl<P> Frame<P> {
@@ -157,6 +610,90 @@ impl<P> Frame<P> {
     }
 }
 
+impl<P> Frame<P>
+where
+    for<'a> &'a P: Into<Rgb>,
+{
+    /// Encodes this frame as an 8-bit RGB PNG and writes it to `writer`.
+    pub fn save_png<W: Write>(&self, writer: W) -> std::io::Result<()> {
+        let (width, height) = self.resolution;
+
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        self.data
+            .iter()
+            .for_each(|pixel| rgb.extend_from_slice(&<&P as Into<Rgb>>::into(pixel).vals));
+
+        png::write_png(writer, width as u32, height as u32, &rgb)
+    }
+}
+
+impl<P: Copy> Frame<P>
+where
+    for<'a> &'a P: Into<Rgb>,
+{
+    /// Replaces every pixel whose HSV value falls within the given (inclusive)
+    /// hue/saturation/value ranges with `replacement`.
+    ///
+    /// `hue` is in degrees and wraps around `0.0..360.0`, so a range like
+    /// `(350.0, 10.0)` spans the red region across the 360°/0° boundary.
+    /// `sat` and `val` are fractions in `0.0..=1.0` and don't wrap.
+    pub fn replace_hsv_range(
+        &mut self,
+        hue: (f64, f64),
+        sat: (f64, f64),
+        val: (f64, f64),
+        replacement: P,
+    ) {
+        for pixel in &mut self.data {
+            let hsv: Hsv = <&P as Into<Rgb>>::into(&*pixel).into();
+
+            if hsv_in_range(&hsv, hue, sat, val) {
+                *pixel = replacement;
+            }
+        }
+    }
+
+    /// Returns the (x, y) coordinates of every pixel whose HSV value falls within
+    /// the given ranges. See [`Frame::replace_hsv_range`] for how ranges are
+    /// interpreted.
+    pub fn detect_hsv_range(
+        &self,
+        hue: (f64, f64),
+        sat: (f64, f64),
+        val: (f64, f64),
+    ) -> Vec<(usize, usize)> {
+        let width = self.resolution.0;
+
+        self.data
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pixel)| {
+                let hsv: Hsv = <&P as Into<Rgb>>::into(pixel).into();
+
+                hsv_in_range(&hsv, hue, sat, val).then(|| (i % width, i / width))
+            })
+            .collect()
+    }
+}
+
+fn hsv_in_range(hsv: &Hsv, hue: (f64, f64), sat: (f64, f64), val: (f64, f64)) -> bool {
+    in_range_wrapping(hsv.hue_degrees(), hue)
+        && in_range(hsv.saturation(), sat)
+        && in_range(hsv.value(), val)
+}
+
+fn in_range(value: f64, (lo, hi): (f64, f64)) -> bool {
+    value >= lo && value <= hi
+}
+
+fn in_range_wrapping(value: f64, (lo, hi): (f64, f64)) -> bool {
+    if lo <= hi {
+        in_range(value, (lo, hi))
+    } else {
+        value >= lo || value <= hi
+    }
+}
+
 impl<P> Index<(usize, usize)> for Frame<P> {
     type Output = P;
 
@@ -176,3 +713,111 @@ impl<P> IndexMut<(usize, usize)> for Frame<P> {
         self.get_mut(x, y).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_plan_falls_back_to_the_software_codec() {
+        for codec in [Codec::H264, Codec::H265, Codec::Av1] {
+            let builder = VideoBuilder::<Rgb>::new((1, 1), 30).codec(codec);
+            let (pre_input_args, input_filter, codec_name, owns_pix_fmt) = builder.encode_plan();
+
+            assert!(pre_input_args.is_empty());
+            assert_eq!(input_filter, None);
+            assert_eq!(codec_name, codec.ffmpeg_name());
+            assert!(owns_pix_fmt);
+            assert!(!builder.hw_accel_active());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "vaapi")]
+    fn encode_plan_vaapi_uploads_to_a_hardware_surface_and_gives_up_pix_fmt() {
+        let builder = VideoBuilder::<Rgb>::new((1, 1), 30).hw_accel(HwAccel::Vaapi {
+            device: "/dev/dri/renderD128".to_string(),
+            hevc: false,
+        });
+        let (pre_input_args, input_filter, codec_name, owns_pix_fmt) = builder.encode_plan();
+
+        assert_eq!(
+            pre_input_args,
+            vec![
+                "-vaapi_device".to_string(),
+                "/dev/dri/renderD128".to_string()
+            ]
+        );
+        assert_eq!(input_filter, Some("format=nv12,hwupload".to_string()));
+        assert_eq!(codec_name, "h264_vaapi");
+        assert!(!owns_pix_fmt);
+        assert!(builder.hw_accel_active());
+
+        let hevc = VideoBuilder::<Rgb>::new((1, 1), 30)
+            .hw_accel(HwAccel::Vaapi {
+                device: "/dev/dri/renderD128".to_string(),
+                hevc: true,
+            })
+            .encode_plan();
+        assert_eq!(hevc.2, "hevc_vaapi");
+    }
+
+    #[test]
+    #[cfg(feature = "vaapi")]
+    fn encode_plan_nvenc_keeps_pix_fmt_but_drops_crf() {
+        let builder = VideoBuilder::<Rgb>::new((1, 1), 30).hw_accel(HwAccel::Nvenc);
+        let (pre_input_args, input_filter, codec_name, owns_pix_fmt) = builder.encode_plan();
+
+        assert!(pre_input_args.is_empty());
+        assert_eq!(input_filter, None);
+        assert_eq!(codec_name, "h264_nvenc");
+        assert!(owns_pix_fmt);
+        assert!(builder.hw_accel_active());
+    }
+
+    #[test]
+    fn pan_filter_extracts_the_selected_channel_only_when_not_both() {
+        assert_eq!(AudioChannel::Both.pan_filter(), None);
+        assert_eq!(AudioChannel::Left.pan_filter(), Some("pan=mono|c0=c0"));
+        assert_eq!(AudioChannel::Right.pan_filter(), Some("pan=mono|c0=c1"));
+    }
+
+    #[test]
+    fn in_range_is_inclusive_of_both_ends() {
+        assert!(in_range(0.0, (0.0, 1.0)));
+        assert!(in_range(1.0, (0.0, 1.0)));
+        assert!(in_range(0.5, (0.0, 1.0)));
+        assert!(!in_range(-0.01, (0.0, 1.0)));
+        assert!(!in_range(1.01, (0.0, 1.0)));
+    }
+
+    #[test]
+    fn in_range_wrapping_handles_non_wrapping_ranges_like_in_range() {
+        assert!(in_range_wrapping(180.0, (90.0, 270.0)));
+        assert!(!in_range_wrapping(45.0, (90.0, 270.0)));
+    }
+
+    #[test]
+    fn in_range_wrapping_spans_the_0_360_boundary() {
+        // A red hue range that wraps around 0/360 degrees.
+        assert!(in_range_wrapping(355.0, (350.0, 10.0)));
+        assert!(in_range_wrapping(0.0, (350.0, 10.0)));
+        assert!(in_range_wrapping(10.0, (350.0, 10.0)));
+        assert!(!in_range_wrapping(180.0, (350.0, 10.0)));
+    }
+
+    #[test]
+    fn replace_and_detect_hsv_range_agree_on_matching_pixels() {
+        let mut frame: Frame<Rgb> = Frame::new((2, 1));
+        frame[(0, 0)] = Rgb::bytes([255, 0, 0]); // pure red: hue 0
+        frame[(1, 0)] = Rgb::bytes([0, 255, 0]); // pure green: hue 120
+
+        let red_range = ((350.0, 10.0), (0.0, 1.0), (0.0, 1.0));
+        let detected = frame.detect_hsv_range(red_range.0, red_range.1, red_range.2);
+        assert_eq!(detected, vec![(0, 0)]);
+
+        frame.replace_hsv_range(red_range.0, red_range.1, red_range.2, Rgb::bytes([0, 0, 0]));
+        assert_eq!(frame[(0, 0)], Rgb::bytes([0, 0, 0]));
+        assert_eq!(frame[(1, 0)], Rgb::bytes([0, 255, 0]));
+    }
+}
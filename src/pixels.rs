@@ -1,12 +1,15 @@
 //! Basic kinds of pixels, offering variation in the choice of frames.  
 //! HSL and HSV algorithms adapted from [Wikipedia](https://en.wikipedia.org/wiki/HSL_and_HSV).
 //!
-//! Every type of pixel must satisfy the trait bound `Into<Rgb>`, where `Rgb` is the struct in this module.
+//! Every type of pixel must implement [`PixelFormat`], which drives the ffmpeg
+//! `-pixel_format` a [`crate::Video`] is piped with. Pixel types with no matching raw
+//! ffmpeg format (like [`Hsl`] and [`Hsv`]) fall back to converting to [`Rgb`] and
+//! reporting `rgb24`.
 
 /// A simple RGB pixel.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 pub struct Rgb {
-    vals: [u8; 3],
+    pub(crate) vals: [u8; 3],
 }
 
 impl Rgb {
@@ -25,8 +28,14 @@ impl Rgb {
     }
 }
 
+impl From<&Rgb> for Rgb {
+    fn from(other: &Rgb) -> Self {
+        *other
+    }
+}
+
 /// An HSL (Hue, Saturation, Lightness) pixel.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Hsl {
     vals: [f64; 3],
 }
@@ -61,7 +70,7 @@ impl Hsl {
 }
 
 /// An HSV (Hue, Saturation, Value/Brightness) pixel.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Hsv {
     vals: [f64; 3],
 }
@@ -113,6 +122,58 @@ impl From<Hsl> for Rgb {
     }
 }
 
+impl From<&Hsl> for Rgb {
+    fn from(other: &Hsl) -> Self {
+        Rgb::from(*other)
+    }
+}
+
+impl Hsv {
+    /// Returns this pixel's hue in degrees, in the range `0.0..=360.0`.
+    pub fn hue_degrees(&self) -> f64 {
+        self.vals[0] * 360.
+    }
+
+    /// Returns this pixel's saturation as a fraction in `0.0..=1.0`.
+    pub fn saturation(&self) -> f64 {
+        self.vals[1]
+    }
+
+    /// Returns this pixel's value (brightness) as a fraction in `0.0..=1.0`.
+    pub fn value(&self) -> f64 {
+        self.vals[2]
+    }
+}
+
+impl From<Rgb> for Hsv {
+    #[allow(clippy::many_single_char_names)]
+    fn from(other: Rgb) -> Self {
+        let [r, g, b] = other.vals;
+        let (r, g, b) = (r as f64 / 255., g as f64 / 255., b as f64 / 255.);
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let v = max;
+        let s = if max == 0. { 0. } else { delta / max };
+
+        let h = if delta == 0. {
+            0.
+        } else if max == r {
+            60. * ((g - b) / delta).rem_euclid(6.)
+        } else if max == g {
+            60. * ((b - r) / delta + 2.)
+        } else {
+            60. * ((r - g) / delta + 4.)
+        };
+
+        Self {
+            vals: [h / 360., s, v],
+        }
+    }
+}
+
 impl From<Hsv> for Rgb {
     #[allow(clippy::many_single_char_names)]
     fn from(other: Hsv) -> Self {
@@ -134,6 +195,231 @@ impl From<Hsv> for Rgb {
     }
 }
 
+impl From<&Hsv> for Rgb {
+    fn from(other: &Hsv) -> Self {
+        Rgb::from(*other)
+    }
+}
+
 fn to_u8(fl: f64) -> u8 {
     (fl * 254.99).round().clamp(0., 255.) as u8
 }
+
+/// Describes how to lay out a pixel type as raw bytes ffmpeg can read directly,
+/// without going through [`Rgb`] first.
+///
+/// Every pixel type used with [`crate::Video`] must implement this; the
+/// `-pixel_format` ffmpeg is given matches [`PixelFormat::FFMPEG_NAME`] for the
+/// chosen `P`, so the hot loop in `Video::save_frame` can write each pixel's
+/// native bytes instead of always converting to `rgb24`.
+pub trait PixelFormat {
+    /// The `-pixel_format` name ffmpeg should use for this layout.
+    const FFMPEG_NAME: &'static str;
+
+    /// Appends this pixel's raw bytes, in ffmpeg's expected order, to `buf`.
+    fn write_raw(&self, buf: &mut Vec<u8>);
+}
+
+impl PixelFormat for Rgb {
+    const FFMPEG_NAME: &'static str = "rgb24";
+
+    fn write_raw(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.vals);
+    }
+}
+
+impl PixelFormat for Hsl {
+    const FFMPEG_NAME: &'static str = "rgb24";
+
+    fn write_raw(&self, buf: &mut Vec<u8>) {
+        Rgb::from(*self).write_raw(buf);
+    }
+}
+
+impl PixelFormat for Hsv {
+    const FFMPEG_NAME: &'static str = "rgb24";
+
+    fn write_raw(&self, buf: &mut Vec<u8>) {
+        Rgb::from(*self).write_raw(buf);
+    }
+}
+
+/// A BGR pixel, matching ffmpeg's `bgr24` byte order.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Bgr {
+    vals: [u8; 3],
+}
+
+impl Bgr {
+    /// Construct a new BGR pixel from a triplet of bytes, in B, G, R order.
+    pub fn bytes(bytes: [u8; 3]) -> Self {
+        Self { vals: bytes }
+    }
+}
+
+impl From<&Bgr> for Rgb {
+    fn from(other: &Bgr) -> Self {
+        Rgb::bytes([other.vals[2], other.vals[1], other.vals[0]])
+    }
+}
+
+impl PixelFormat for Bgr {
+    const FFMPEG_NAME: &'static str = "bgr24";
+
+    fn write_raw(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.vals);
+    }
+}
+
+/// An RGBA pixel, matching ffmpeg's `rgba` byte order.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rgba {
+    vals: [u8; 4],
+}
+
+impl Rgba {
+    /// Construct a new RGBA pixel from a quadruplet of bytes.
+    pub fn bytes(bytes: [u8; 4]) -> Self {
+        Self { vals: bytes }
+    }
+}
+
+impl From<&Rgba> for Rgb {
+    fn from(other: &Rgba) -> Self {
+        Rgb::bytes([other.vals[0], other.vals[1], other.vals[2]])
+    }
+}
+
+impl PixelFormat for Rgba {
+    const FFMPEG_NAME: &'static str = "rgba";
+
+    fn write_raw(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.vals);
+    }
+}
+
+/// An 8-bit grayscale pixel, matching ffmpeg's `gray` pixel format.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Gray8 {
+    val: u8,
+}
+
+impl Gray8 {
+    /// Construct a new grayscale pixel from a single byte.
+    pub fn byte(val: u8) -> Self {
+        Self { val }
+    }
+}
+
+impl From<&Gray8> for Rgb {
+    fn from(other: &Gray8) -> Self {
+        Rgb::bytes([other.val; 3])
+    }
+}
+
+impl PixelFormat for Gray8 {
+    const FFMPEG_NAME: &'static str = "gray";
+
+    fn write_raw(&self, buf: &mut Vec<u8>) {
+        buf.push(self.val);
+    }
+}
+
+/// A packed 15-bit pixel with 5-bit R/G/B channels, matching ffmpeg's
+/// `rgb555le` pixel format (the leftover top bit is unused padding).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Pixel16 {
+    val: u16,
+}
+
+impl Pixel16 {
+    /// Packs 5-bit R, G, B channels (each in `0..=31`) into a [`Pixel16`].
+    pub fn pack(r: u8, g: u8, b: u8) -> Self {
+        let r = (r & 0x1f) as u16;
+        let g = (g & 0x1f) as u16;
+        let b = (b & 0x1f) as u16;
+
+        Self {
+            val: (r << 10) | (g << 5) | b,
+        }
+    }
+
+    /// Unpacks this pixel into its 5-bit R, G, B channels (each in `0..=31`).
+    pub fn unpack(&self) -> (u8, u8, u8) {
+        let r = ((self.val >> 10) & 0x1f) as u8;
+        let g = ((self.val >> 5) & 0x1f) as u8;
+        let b = (self.val & 0x1f) as u8;
+
+        (r, g, b)
+    }
+}
+
+impl From<&Pixel16> for Rgb {
+    fn from(other: &Pixel16) -> Self {
+        let (r, g, b) = other.unpack();
+        let scale = |c: u8| (c as u32 * 255 / 31) as u8;
+
+        Rgb::bytes([scale(r), scale(g), scale(b)])
+    }
+}
+
+impl PixelFormat for Pixel16 {
+    const FFMPEG_NAME: &'static str = "rgb555le";
+
+    fn write_raw(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.val.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bgr_write_raw_keeps_byte_order_and_converts_to_rgb() {
+        let bgr = Bgr::bytes([1, 2, 3]);
+
+        let mut buf = Vec::new();
+        bgr.write_raw(&mut buf);
+        assert_eq!(buf, [1, 2, 3]);
+
+        assert_eq!(Rgb::from(&bgr), Rgb::bytes([3, 2, 1]));
+    }
+
+    #[test]
+    fn rgba_write_raw_keeps_all_four_bytes_and_drops_alpha_for_rgb() {
+        let rgba = Rgba::bytes([10, 20, 30, 40]);
+
+        let mut buf = Vec::new();
+        rgba.write_raw(&mut buf);
+        assert_eq!(buf, [10, 20, 30, 40]);
+
+        assert_eq!(Rgb::from(&rgba), Rgb::bytes([10, 20, 30]));
+    }
+
+    #[test]
+    fn gray8_write_raw_writes_one_byte_and_converts_to_a_neutral_rgb() {
+        let gray = Gray8::byte(128);
+
+        let mut buf = Vec::new();
+        gray.write_raw(&mut buf);
+        assert_eq!(buf, [128]);
+
+        assert_eq!(Rgb::from(&gray), Rgb::bytes([128, 128, 128]));
+    }
+
+    #[test]
+    fn pixel16_pack_unpack_round_trips_and_write_raw_is_little_endian() {
+        let pixel = Pixel16::pack(0x1f, 0x0a, 0x00);
+        assert_eq!(pixel.unpack(), (0x1f, 0x0a, 0x00));
+
+        // out-of-range channel bits are masked off rather than overflowing into
+        // their neighbor.
+        let masked = Pixel16::pack(0xff, 0, 0);
+        assert_eq!(masked.unpack(), (0x1f, 0, 0));
+
+        let mut buf = Vec::new();
+        pixel.write_raw(&mut buf);
+        assert_eq!(buf, pixel.val.to_le_bytes());
+    }
+}
@@ -0,0 +1,170 @@
+//! A minimal PNG encoder: just enough to emit an 8-bit truecolor (RGB) image,
+//! with every scanline using filter type 0 (None) and every IDAT block stored
+//! (uncompressed) rather than actually deflate-compressed.
+
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Writes `rgb` (tightly packed, row-major, 3 bytes per pixel) as a PNG to `writer`.
+pub(crate) fn write_png<W: Write>(
+    mut writer: W,
+    width: u32,
+    height: u32,
+    rgb: &[u8],
+) -> io::Result<()> {
+    writer.write_all(&SIGNATURE)?;
+
+    write_chunk(&mut writer, b"IHDR", &ihdr_data(width, height))?;
+    write_chunk(&mut writer, b"IDAT", &idat_data(width, rgb))?;
+    write_chunk(&mut writer, b"IEND", &[])?;
+
+    Ok(())
+}
+
+fn ihdr_data(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(13);
+    data.extend_from_slice(&width.to_be_bytes());
+    data.extend_from_slice(&height.to_be_bytes());
+    data.push(8); // bit depth
+    data.push(2); // color type: truecolor (RGB)
+    data.push(0); // compression method
+    data.push(0); // filter method
+    data.push(0); // interlace method
+    data
+}
+
+fn idat_data(width: u32, rgb: &[u8]) -> Vec<u8> {
+    if rgb.is_empty() {
+        return zlib_store(&[]);
+    }
+
+    let stride = width as usize * 3;
+
+    let mut raw = Vec::with_capacity(rgb.len() + rgb.len() / stride.max(1) + 1);
+    for row in rgb.chunks_exact(stride) {
+        raw.push(0); // filter type 0: None
+        raw.extend_from_slice(row);
+    }
+
+    zlib_store(&raw)
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+
+    let mut crc_input = Vec::with_capacity(4 + data.len());
+    crc_input.extend_from_slice(kind);
+    crc_input.extend_from_slice(data);
+    writer.write_all(&crc32(&crc_input).to_be_bytes())?;
+
+    Ok(())
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed ("stored") deflate blocks.
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+
+    let block_count = (data.len() + MAX_BLOCK - 1).max(1) / MAX_BLOCK.max(1);
+    let block_count = block_count.max(1);
+
+    let mut out = Vec::with_capacity(data.len() + block_count * 5 + 6);
+    out.push(0x78); // CMF: deflate, 32k window
+    out.push(0x01); // FLG: makes (CMF << 8 | FLG) a multiple of 31
+
+    let mut chunks = data.chunks(MAX_BLOCK);
+    let mut chunk = chunks.next().unwrap_or(&[]);
+    let mut remaining = block_count - 1;
+
+    loop {
+        let is_last = remaining == 0;
+        out.push(is_last as u8);
+        out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+        out.extend_from_slice(chunk);
+
+        if is_last {
+            break;
+        }
+        chunk = chunks.next().unwrap_or(&[]);
+        remaining -= 1;
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in data {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+
+    (b << 16) | a
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_known_vector() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn adler32_matches_known_vector() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn zlib_store_round_trips_stored_blocks() {
+        let data = b"hello, stored deflate!".to_vec();
+        let zlib = zlib_store(&data);
+
+        // 2-byte zlib header, then one stored deflate block: 1 byte of
+        // BFINAL/BTYPE, a 2-byte LEN, its one's-complement NLEN, the raw
+        // bytes, and finally a 4-byte Adler-32 trailer.
+        assert_eq!(zlib[0], 0x78);
+        assert_eq!(zlib[1], 0x01);
+
+        let block = &zlib[2..];
+        assert_eq!(block[0], 1); // BFINAL = 1, BTYPE = 00 (stored)
+        let len = u16::from_le_bytes([block[1], block[2]]);
+        let nlen = u16::from_le_bytes([block[3], block[4]]);
+        assert_eq!(len as usize, data.len());
+        assert_eq!(nlen, !len);
+        assert_eq!(&block[5..5 + data.len()], &data[..]);
+
+        let trailer = &zlib[zlib.len() - 4..];
+        assert_eq!(
+            u32::from_be_bytes(trailer.try_into().unwrap()),
+            adler32(&data)
+        );
+    }
+
+    #[test]
+    fn idat_data_on_empty_image_does_not_panic() {
+        // width == 0 would make chunks_exact(0) panic if not guarded.
+        let _ = idat_data(0, &[]);
+    }
+}
@@ -0,0 +1,194 @@
+//! A pure-Rust alternative to the ffmpeg-backed [`crate::Video`], writing an
+//! uncompressed [YUV4MPEG2](https://wiki.multimedia.cx/index.php/YUV4MPEG2) (Y4M)
+//! stream to an arbitrary [`Write`] sink. This lets the crate hand off a standard,
+//! widely-readable intermediate file on systems without ffmpeg installed, or feed
+//! a later ffmpeg pass itself.
+
+use std::io::Write;
+
+use crate::pixels::Rgb;
+use crate::Frame;
+
+/// Represents an entire video being written out as a Y4M stream to a `W: Write` sink.
+pub struct Y4mVideo<P, W: Write> {
+    buffer: Frame<P>,
+    resolution: (usize, usize),
+    fps: u32,
+    sink: W,
+}
+
+impl<P: Default, W: Write> Y4mVideo<P, W>
+where
+    for<'a> &'a P: Into<Rgb>,
+{
+    /// Creates a new empty Y4M video with the given resolution and FPS, writing the
+    /// `YUV4MPEG2` stream header to `sink` immediately.
+    pub fn new(resolution: (usize, usize), fps: u32, mut sink: W) -> Self {
+        let (x, y) = resolution;
+
+        writeln!(sink, "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg", x, y, fps)
+            .expect("could not write Y4M header");
+
+        Self {
+            buffer: Frame::new(resolution),
+            resolution,
+            fps,
+            sink,
+        }
+    }
+
+    /// Resets the frame buffer and returns a mutable reference to it.
+    pub fn reset_frame(&mut self) -> &mut Frame<P> {
+        self.buffer.reset();
+
+        &mut self.buffer
+    }
+
+    /// Returns a mutable reference to the current frame buffer without modifying it.
+    pub fn get_frame_mut(&mut self) -> &mut Frame<P> {
+        &mut self.buffer
+    }
+
+    /// Returns this video's resolution as an (x, y) tuple.
+    pub fn get_resolution(&self) -> (usize, usize) {
+        self.resolution
+    }
+
+    /// Returns this video's framerate in FPS (Frames Per Second).
+    pub fn get_fps(&self) -> u32 {
+        self.fps
+    }
+
+    /// Converts the current frame to planar 4:2:0 YCbCr (BT.601) and writes it as a
+    /// `FRAME` to the sink. Chroma samples are averaged over 2x2 luma blocks.
+    pub fn save_frame(&mut self) {
+        let (w, h) = self.resolution;
+        let cw = w.div_ceil(2);
+        let ch = h.div_ceil(2);
+
+        let mut y_plane = vec![0u8; w * h];
+        let mut u_acc = vec![0f64; cw * ch];
+        let mut v_acc = vec![0f64; cw * ch];
+        let mut count = vec![0u32; cw * ch];
+
+        for row in 0..h {
+            for col in 0..w {
+                let rgb = <&P as Into<Rgb>>::into(&self.buffer[(col, row)]).vals;
+                let (r, g, b) = (rgb[0] as f64, rgb[1] as f64, rgb[2] as f64);
+
+                let y = 0.299 * r + 0.587 * g + 0.114 * b;
+                let u = 128.0 - 0.169 * r - 0.331 * g + 0.5 * b;
+                let v = 128.0 + 0.5 * r - 0.419 * g - 0.081 * b;
+
+                y_plane[row * w + col] = to_u8(y);
+
+                let ci = (row / 2) * cw + (col / 2);
+                u_acc[ci] += u;
+                v_acc[ci] += v;
+                count[ci] += 1;
+            }
+        }
+
+        let u_plane = average_plane(&u_acc, &count);
+        let v_plane = average_plane(&v_acc, &count);
+
+        self.sink
+            .write_all(b"FRAME\n")
+            .expect("could not write to Y4M sink");
+        self.sink
+            .write_all(&y_plane)
+            .expect("could not write to Y4M sink");
+        self.sink
+            .write_all(&u_plane)
+            .expect("could not write to Y4M sink");
+        self.sink
+            .write_all(&v_plane)
+            .expect("could not write to Y4M sink");
+    }
+
+    /// Flushes the sink and returns it.
+    pub fn finish(mut self) -> W {
+        self.sink.flush().expect("could not flush Y4M sink");
+        self.sink
+    }
+}
+
+fn average_plane(sums: &[f64], counts: &[u32]) -> Vec<u8> {
+    sums.iter()
+        .zip(counts)
+        .map(|(sum, count)| to_u8(sum / *count as f64))
+        .collect()
+}
+
+fn to_u8(val: f64) -> u8 {
+    val.round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixels::Rgb;
+
+    #[test]
+    fn to_u8_rounds_and_clamps() {
+        assert_eq!(to_u8(0.0), 0);
+        assert_eq!(to_u8(254.6), 255);
+        assert_eq!(to_u8(-10.0), 0);
+        assert_eq!(to_u8(300.0), 255);
+    }
+
+    #[test]
+    fn average_plane_averages_grouped_sums() {
+        let sums = [30.0, 90.0];
+        let counts = [3, 2];
+
+        assert_eq!(average_plane(&sums, &counts), vec![10, 45]);
+    }
+
+    #[test]
+    fn save_frame_writes_header_and_averaged_chroma() {
+        let mut video = Y4mVideo::<Rgb, Vec<u8>>::new((2, 2), 30, Vec::new());
+
+        video.get_frame_mut()[(0, 0)] = Rgb::bytes([255, 0, 0]);
+        video.get_frame_mut()[(1, 0)] = Rgb::bytes([0, 255, 0]);
+        video.get_frame_mut()[(0, 1)] = Rgb::bytes([0, 0, 255]);
+        video.get_frame_mut()[(1, 1)] = Rgb::bytes([255, 255, 255]);
+        video.save_frame();
+
+        let out = video.finish();
+        let header_end = out.iter().position(|&b| b == b'\n').unwrap() + 1;
+        assert_eq!(
+            &out[..header_end],
+            b"YUV4MPEG2 W2 H2 F30:1 Ip A1:1 C420jpeg\n"
+        );
+
+        let frame = &out[header_end..];
+        assert_eq!(&frame[..6], b"FRAME\n");
+
+        // 2x2 luma, then a single averaged 1x1 chroma sample pair.
+        let y_plane = &frame[6..10];
+        let u_plane = frame[10];
+        let v_plane = frame[11];
+
+        let expected_y = |r: f64, g: f64, b: f64| to_u8(0.299 * r + 0.587 * g + 0.114 * b);
+        assert_eq!(y_plane[0], expected_y(255.0, 0.0, 0.0));
+        assert_eq!(y_plane[1], expected_y(0.0, 255.0, 0.0));
+        assert_eq!(y_plane[2], expected_y(0.0, 0.0, 255.0));
+        assert_eq!(y_plane[3], expected_y(255.0, 255.0, 255.0));
+
+        let u = |r: f64, g: f64, b: f64| 128.0 - 0.169 * r - 0.331 * g + 0.5 * b;
+        let v = |r: f64, g: f64, b: f64| 128.0 + 0.5 * r - 0.419 * g - 0.081 * b;
+        let pixels = [
+            (255.0, 0.0, 0.0),
+            (0.0, 255.0, 0.0),
+            (0.0, 0.0, 255.0),
+            (255.0, 255.0, 255.0),
+        ];
+        let expected_u = to_u8(pixels.iter().map(|&(r, g, b)| u(r, g, b)).sum::<f64>() / 4.0);
+        let expected_v = to_u8(pixels.iter().map(|&(r, g, b)| v(r, g, b)).sum::<f64>() / 4.0);
+
+        assert_eq!(u_plane, expected_u);
+        assert_eq!(v_plane, expected_v);
+        assert_eq!(frame.len(), 12);
+    }
+}